@@ -0,0 +1,69 @@
+use std::fmt;
+
+use crate::PairConfig;
+
+/// Why an alert fired, carrying enough detail to tag the Telegram message.
+pub(crate) enum AlertReason {
+    Above { threshold: f64, price: f64 },
+    Below { threshold: f64, price: f64 },
+    ChangePct { threshold_pct: f64, actual_pct: f64 },
+}
+
+impl fmt::Display for AlertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertReason::Above { threshold, price } => {
+                write!(f, "price {} crossed above {}", price, threshold)
+            }
+            AlertReason::Below { threshold, price } => {
+                write!(f, "price {} crossed below {}", price, threshold)
+            }
+            AlertReason::ChangePct {
+                threshold_pct,
+                actual_pct,
+            } => write!(
+                f,
+                "price moved {:.2}% (threshold {:.2}%)",
+                actual_pct, threshold_pct
+            ),
+        }
+    }
+}
+
+/// Checks a new price against a pair's configured alert rules. `previous` is
+/// the last price that was stored for this pair, if any. Returns the first
+/// rule that fires, or `None` if nothing warrants an alert.
+///
+/// `alert_above`/`alert_below` are edge-triggered on `previous`, like
+/// `alert_change_pct` already is: without a previous price to compare
+/// against (the pair's first tick), no threshold alert fires, since there's
+/// no crossing to report yet. Without this, a pair sitting above/below its
+/// bound would re-fire on every single tick of the now per-trade WebSocket
+/// feed instead of once on the crossing.
+pub(crate) fn evaluate(config: &PairConfig, previous: Option<f64>, price: f64) -> Option<AlertReason> {
+    if let (Some(threshold), Some(previous)) = (config.alert_above, previous) {
+        if price > threshold && previous <= threshold {
+            return Some(AlertReason::Above { threshold, price });
+        }
+    }
+
+    if let (Some(threshold), Some(previous)) = (config.alert_below, previous) {
+        if price < threshold && previous >= threshold {
+            return Some(AlertReason::Below { threshold, price });
+        }
+    }
+
+    if let (Some(threshold_pct), Some(previous)) = (config.alert_change_pct, previous) {
+        if previous != 0.0 {
+            let actual_pct = ((price - previous) / previous) * 100.0;
+            if actual_pct.abs() >= threshold_pct {
+                return Some(AlertReason::ChangePct {
+                    threshold_pct,
+                    actual_pct,
+                });
+            }
+        }
+    }
+
+    None
+}
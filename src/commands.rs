@@ -0,0 +1,116 @@
+use redis::Commands;
+use teloxide::prelude::*;
+use teloxide::types::UpdateKind;
+
+use crate::{PairConfig, PriceData};
+
+/// Polls Telegram for updates and answers `/price <PAIR>`, `/history <PAIR>`
+/// and `/pairs` on demand, from the configured `chat_id` only. Meant to run
+/// alongside the price-monitoring loop via `tokio::select!`, over its own
+/// Redis connection (not shared with the monitor loop).
+///
+/// Runs forever: a transient long-poll failure or a reply that Telegram
+/// rejects (e.g. `/history` exceeding the 4096-char message limit) is logged
+/// and skipped rather than propagated, since an `Err` out of here would end
+/// the `select!` in `main` and take the price-monitoring loop down with it.
+pub(crate) async fn run(bot: Bot, chat_id: i64, mut con: redis::Connection, pairs: Vec<PairConfig>) -> ! {
+    let mut offset = 0;
+
+    loop {
+        let updates = match bot.get_updates().offset(offset).timeout(30).send().await {
+            Ok(updates) => updates,
+            Err(e) => {
+                eprintln!("Failed to poll Telegram updates: {}", e);
+                continue;
+            }
+        };
+
+        for update in updates {
+            offset = update.id + 1;
+
+            let UpdateKind::Message(msg) = update.kind else {
+                continue;
+            };
+
+            if msg.chat.id.0 != chat_id {
+                continue;
+            }
+
+            let Some(text) = msg.text() else {
+                continue;
+            };
+
+            if let Some(reply) = handle_command(text, &mut con, &pairs) {
+                if let Err(e) = bot.send_message(msg.chat.id, reply).await {
+                    eprintln!("Failed to send Telegram reply: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn handle_command(text: &str, con: &mut redis::Connection, pairs: &[PairConfig]) -> Option<String> {
+    let mut parts = text.split_whitespace();
+    let command = parts.next()?;
+    let arg = parts.next();
+
+    match command {
+        "/price" => Some(handle_price(con, arg)),
+        "/history" => Some(handle_history(con, arg)),
+        "/pairs" => Some(handle_pairs(pairs)),
+        _ => None,
+    }
+}
+
+fn handle_price(con: &mut redis::Connection, pair: Option<&str>) -> String {
+    let Some(pair) = pair else {
+        return "Usage: /price <PAIR>".to_string();
+    };
+
+    let json: Option<String> = match con.get(pair) {
+        Ok(json) => json,
+        Err(e) => return format!("Failed to read price for {}: {}", pair, e),
+    };
+
+    match json.and_then(|json| serde_json::from_str::<PriceData>(&json).ok()) {
+        Some(data) => format!("{}: ${} ({})", pair, data.price, data.display_time()),
+        None => format!("No cached price for {}", pair),
+    }
+}
+
+fn handle_history(con: &mut redis::Connection, pair: Option<&str>) -> String {
+    let Some(pair) = pair else {
+        return "Usage: /history <PAIR>".to_string();
+    };
+
+    let key = format!("history:{}", pair);
+    // The history sorted set is keyed by epoch, so this already comes back
+    // oldest-first without needing to sort by a lexically-formatted string.
+    let entries: Vec<String> = match con.zrangebyscore(&key, "-inf", "+inf") {
+        Ok(entries) => entries,
+        Err(e) => return format!("Failed to read history for {}: {}", pair, e),
+    };
+
+    if entries.is_empty() {
+        return format!("No history for {}", pair);
+    }
+
+    entries
+        .into_iter()
+        .filter_map(|json| serde_json::from_str::<PriceData>(&json).ok())
+        .map(|data| format!("{} - ${}", data.display_time(), data.price))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn handle_pairs(pairs: &[PairConfig]) -> String {
+    if pairs.is_empty() {
+        return "No pairs configured".to_string();
+    }
+
+    pairs
+        .iter()
+        .map(|p| p.pair.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
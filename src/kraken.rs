@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoff;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::sync::watch;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::PairConfig;
+
+const KRAKEN_WS_URL: &str = "wss://ws.kraken.com";
+
+/// Normalizes a pair name for matching a configured pair against the name
+/// Kraken echoes back on the wire. Kraken's WS wsname ("XBT/USD") and a
+/// REST-style config entry ("XBTUSD") can disagree on separators and
+/// casing for the same pair, so both sides are compared in this form
+/// rather than assuming `config.pair` is already Kraken's wsname.
+fn normalize_pair(pair: &str) -> String {
+    pair.chars().filter(|c| *c != '/').collect::<String>().to_uppercase()
+}
+
+/// A price freshly observed on the stream.
+#[derive(Debug, Clone)]
+pub struct PriceUpdate {
+    pub pair: String,
+    pub price: String,
+}
+
+/// What a subscriber can observe on the stream: either a fresh price, or a
+/// sentinel meaning reconnection has been given up on for good. Subscribers
+/// never see the raw connection/parse errors that happen in between.
+#[derive(Clone)]
+enum StreamEvent {
+    Price(PriceUpdate),
+    Failed,
+}
+
+type Senders = HashMap<String, watch::Sender<Option<StreamEvent>>>;
+
+/// The receiving half of the live price feed: one `watch` channel per pair,
+/// so a quiet pair can never starve another pair's updates out from behind
+/// a shared, coalescing channel.
+pub struct PriceUpdates {
+    receivers: HashMap<String, watch::Receiver<Option<StreamEvent>>>,
+}
+
+impl PriceUpdates {
+    /// Waits for the next price update for `pair`, skipping the initial
+    /// placeholder. Returns an error only once the stream has permanently
+    /// failed, or if `pair` was never part of the subscribed set.
+    pub async fn next(&mut self, pair: &str) -> Result<PriceUpdate> {
+        let rx = self
+            .receivers
+            .get_mut(&normalize_pair(pair))
+            .with_context(|| format!("no Kraken subscription for pair {}", pair))?;
+
+        loop {
+            rx.changed().await.context("Kraken price stream closed")?;
+            match rx.borrow_and_update().clone() {
+                Some(StreamEvent::Price(update)) => return Ok(update),
+                Some(StreamEvent::Failed) => {
+                    anyhow::bail!("Kraken price stream permanently failed")
+                }
+                None => continue,
+            }
+        }
+    }
+}
+
+/// Opens a persistent WebSocket connection to Kraken and subscribes to
+/// ticker updates for `pairs`. Returns a handle that yields each new price
+/// as it arrives; the connection itself is driven by a background task that
+/// reconnects on its own with exponential backoff.
+pub async fn connect(pairs: Vec<PairConfig>) -> Result<PriceUpdates> {
+    let mut senders = Senders::new();
+    let mut receivers = HashMap::new();
+    for pair_config in &pairs {
+        let (tx, rx) = watch::channel(None);
+        let key = normalize_pair(&pair_config.pair);
+        senders.insert(key.clone(), tx);
+        receivers.insert(key, rx);
+    }
+
+    tokio::spawn(async move {
+        run_with_reconnect(pairs, senders).await;
+    });
+
+    Ok(PriceUpdates { receivers })
+}
+
+/// Drives the connection, reconnecting with exponential backoff (1s initial,
+/// doubling up to a 60s cap, no maximum elapsed time) whenever a
+/// connection-level error occurs. Only sends the `Failed` sentinel if the
+/// backoff policy itself gives up, which the configured policy never does
+/// on its own -- it's kept as a defensive exit path.
+async fn run_with_reconnect(pairs: Vec<PairConfig>, senders: Senders) {
+    let mut backoff = ExponentialBackoff {
+        initial_interval: Duration::from_secs(1),
+        max_interval: Duration::from_secs(60),
+        multiplier: 2.0,
+        max_elapsed_time: None,
+        ..ExponentialBackoff::default()
+    };
+
+    loop {
+        match run_once(&pairs, &senders, &mut backoff).await {
+            Ok(()) => unreachable!("run_once only returns on connection error"),
+            Err(e) => {
+                let delay = match backoff.next_backoff() {
+                    Some(delay) => delay,
+                    None => {
+                        eprintln!("Kraken reconnect attempts exhausted: {}", e);
+                        for tx in senders.values() {
+                            let _ = tx.send(Some(StreamEvent::Failed));
+                        }
+                        return;
+                    }
+                };
+                eprintln!(
+                    "Kraken connection lost ({}), reconnecting in {:?}",
+                    e, delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Runs a single connection attempt to completion. Always returns `Err` on
+/// exit (there's no graceful end to a live ticker stream) and that error is
+/// always connection-level: parse errors on individual frames are logged
+/// inline below and never propagate out of this function, so they never
+/// trigger a reconnect.
+async fn run_once(
+    pairs: &[PairConfig],
+    senders: &Senders,
+    backoff: &mut ExponentialBackoff,
+) -> Result<()> {
+    let (ws_stream, _) = connect_async(KRAKEN_WS_URL)
+        .await
+        .context("Failed to connect to Kraken WebSocket")?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let pair_names: Vec<String> = pairs.iter().map(|p| p.pair.clone()).collect();
+    let subscribe = json!({
+        "event": "subscribe",
+        "pair": pair_names,
+        "subscription": { "name": "ticker" }
+    });
+
+    write
+        .send(Message::Text(subscribe.to_string()))
+        .await
+        .context("Failed to send Kraken subscribe frame")?;
+
+    while let Some(msg) = read.next().await {
+        let msg = msg.context("Kraken WebSocket read error")?;
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Ping(_) | Message::Pong(_) | Message::Binary(_) => continue,
+            Message::Close(_) => anyhow::bail!("Kraken WebSocket closed the connection"),
+            Message::Frame(_) => continue,
+        };
+
+        match parse_ticker(&text) {
+            Ok(Some(update)) => {
+                backoff.reset();
+                // Route to that pair's own channel only, so one silent pair
+                // can never be starved by another pair's updates coalescing
+                // ahead of it. Normalize first: Kraken echoes its own
+                // canonical wsname here (e.g. "XBT/USD"), which doesn't
+                // necessarily match the separator/casing config.yaml uses.
+                if let Some(tx) = senders.get(&normalize_pair(&update.pair)) {
+                    let _ = tx.send(Some(StreamEvent::Price(update)));
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to parse Kraken ticker frame: {}", e),
+        }
+    }
+
+    anyhow::bail!("Kraken WebSocket stream ended")
+}
+
+/// Parses a ticker update frame, extracting the pair name and last-trade
+/// close price. Returns `Ok(None)` for non-ticker frames (subscription acks,
+/// heartbeats, system status messages) and `Err` for frames that look like
+/// a ticker update but couldn't be understood.
+fn parse_ticker(text: &str) -> Result<Option<PriceUpdate>> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+
+    let Some(array) = value.as_array() else {
+        return Ok(None);
+    };
+
+    // Ticker frames look like: [channelID, {...fields...}, "ticker", "PAIR/NAME"]
+    if array.get(2).and_then(|v| v.as_str()) != Some("ticker") {
+        return Ok(None);
+    }
+
+    let fields = array
+        .get(1)
+        .context("ticker frame missing fields object")?;
+    let pair = array
+        .get(3)
+        .and_then(|v| v.as_str())
+        .context("ticker frame missing pair name")?;
+    let price = fields
+        .get("c")
+        .and_then(|c| c.get(0))
+        .and_then(|p| p.as_str())
+        .context("ticker frame missing close price")?;
+
+    Ok(Some(PriceUpdate {
+        pair: pair.to_string(),
+        price: price.to_string(),
+    }))
+}
@@ -5,7 +5,14 @@ use redis::Commands;
 use serde::{Deserialize, Serialize};
 use std::{fs, time::Duration};
 use teloxide::prelude::*;
-use tokio::time;
+
+mod alerts;
+mod commands;
+mod kraken;
+mod price_source;
+mod replay;
+
+use price_source::{FixedRateSource, KrakenSource, PriceSource};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -13,6 +20,23 @@ struct Args {
     /// Replay mode: resend all stored prices from Redis to Telegram
     #[arg(long)]
     replay: bool,
+
+    /// Use the constant `fixed_price` configured per pair instead of live
+    /// Kraken prices (useful for tests or when Kraken is unreachable)
+    #[arg(long)]
+    fixed_rate: bool,
+
+    /// Multiplier for replay pacing: >1.0 compresses, <1.0 expands
+    #[arg(long, default_value_t = 1.0)]
+    replay_speed: f64,
+
+    /// Only replay history at or after this Unix epoch timestamp
+    #[arg(long)]
+    from: Option<i64>,
+
+    /// Only replay history at or before this Unix epoch timestamp
+    #[arg(long)]
+    until: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -20,14 +44,68 @@ struct Config {
     pairs: Vec<PairConfig>,
     telegram: TelegramConfig,
     redis: RedisConfig,
+    /// Price provider to use: "kraken" (default) or "fixed_rate"
+    #[serde(default)]
+    price_source: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct PairConfig {
-    pair: String,
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct PairConfig {
+    pub(crate) pair: String,
     interval: String,
+    /// Constant price used by the `fixed_rate` price source for this pair
+    fixed_price: Option<String>,
+    /// Alert when the price rises above this value
+    alert_above: Option<f64>,
+    /// Alert when the price falls below this value
+    alert_below: Option<f64>,
+    /// Alert when the price moves by at least this many percent since the
+    /// last stored value
+    alert_change_pct: Option<f64>,
+    /// Always send an update for this pair regardless of whether an alert
+    /// rule fired, so users can still opt into periodic updates
+    #[serde(default)]
+    heartbeat: bool,
+    /// How many history entries to keep for this pair. If neither this nor
+    /// `retain_seconds` is set, retention falls back to `DEFAULT_RETAIN_SECONDS`
+    retain_count: Option<i64>,
+    /// How many seconds of history to keep for this pair. If neither this
+    /// nor `retain_count` is set, retention falls back to `DEFAULT_RETAIN_SECONDS`
+    retain_seconds: Option<i64>,
 }
 
+impl PairConfig {
+    /// Parses `interval` (e.g. "30s", "5m", "1h") into a `Duration`, used to
+    /// pace non-streaming price sources. Falls back to 60s if unparseable.
+    fn interval_duration(&self) -> Duration {
+        parse_interval(&self.interval).unwrap_or(Duration::from_secs(60))
+    }
+}
+
+fn parse_interval(interval: &str) -> Option<Duration> {
+    let interval = interval.trim();
+    let split_at = interval.len().checked_sub(1)?;
+    let (value, suffix) = interval.split_at(split_at);
+    let value: u64 = value.parse().ok()?;
+
+    let secs = match suffix {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        "d" => value * 86400,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(secs))
+}
+
+/// Default age-based retention window, applied only when a pair configures
+/// neither `retain_count` nor `retain_seconds`. The feed is now per-trade
+/// WebSocket ticks rather than hourly polls, so a fixed entry count no
+/// longer corresponds to a fixed span of time -- this enforces the actual
+/// "24 hours of history" claim regardless of tick rate.
+const DEFAULT_RETAIN_SECONDS: i64 = 24 * 60 * 60;
+
 #[derive(Debug, Deserialize)]
 struct TelegramConfig {
     bot_token: String,
@@ -40,97 +118,22 @@ struct RedisConfig {
     database: u8,
 }
 
-#[derive(Debug, Deserialize)]
-struct KrakenResponse {
-    result: serde_json::Value,
-    error: Vec<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct PriceData {
-    price: String,
-    timestamp: String,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PriceData {
+    pub(crate) price: String,
+    /// Unix epoch seconds. Stored as a number (rather than a formatted
+    /// string) so it sorts and compares correctly, and so it can be used
+    /// directly as a Redis sorted set score.
+    pub(crate) timestamp: i64,
 }
 
-/*
-async fn replay_data(bot: Bot, chat_id: i64, redis_con: &mut redis::Connection, pairs: &[PairConfig]) -> Result<()> {
-    for pair_config in pairs {
-        let key = format!("history:{}", pair_config.pair);
-        let data: Vec<String> = redis_con.lrange(&key, 0, -1)?;
-        
-        // Convert all entries to PriceData and sort by timestamp
-        let mut price_entries: Vec<(String, PriceData)> = data
-            .into_iter()
-            .filter_map(|json| {
-                serde_json::from_str::<PriceData>(&json)
-                    .ok()
-                    .map(|data| (pair_config.pair.clone(), data))
-            })
-            .collect();
-
-        price_entries.sort_by(|a, b| a.1.timestamp.cmp(&b.1.timestamp));
-
-        // Send each entry to Telegram
-        for (pair, price_data) in price_entries {
-            let message = format!(
-                "🔄 Historical Price\n\nPair: {}\nPrice: ${}\nTime: {}",
-                pair,
-                price_data.price,
-                price_data.timestamp
-            );
-
-            bot.send_message(ChatId(chat_id), message)
-                .await
-                .context("Failed to send Telegram message")?;
-
-            // Add a small delay to avoid hitting Telegram rate limits
-            time::sleep(Duration::from_millis(100)).await;
-        }
-    }
-    Ok(())
-}
-*/
-
-async fn replay_data(bot: Bot, chat_id: i64, redis_con: &mut redis::Connection, pairs: &[PairConfig]) -> Result<()> {
-    const CHUNK_SIZE: isize = 100; // Process 100 entries at a time
-    
-    for pair_config in pairs {
-        let key = format!("history:{}", pair_config.pair);
-        let mut start: isize = 0;
-        
-        loop {
-            // Get a chunk of data
-            let data: Vec<String> = redis_con.lrange(&key, start, start + CHUNK_SIZE - 1)?;
-            
-            // If no more data, break the loop
-            if data.is_empty() {
-                break;
-            }
-            
-            // Process this chunk
-            for json in data {
-                if let Ok(price_data) = serde_json::from_str::<PriceData>(&json) {
-                    let message = format!(
-                        "🔄 Historical Price\n\nPair: {}\nPrice: ${}\nTime: {}",
-                        pair_config.pair,
-                        price_data.price,
-                        price_data.timestamp
-                    );
-
-                    bot.send_message(ChatId(chat_id), message)
-                        .await
-                        .context("Failed to send Telegram message")?;
-
-                    // Add a small delay to avoid hitting Telegram rate limits
-                    time::sleep(Duration::from_millis(100)).await;
-                }
-            }
-            
-            // Move to next chunk
-            start += CHUNK_SIZE;
-        }
+impl PriceData {
+    /// Renders the timestamp for display in Telegram messages.
+    pub(crate) fn display_time(&self) -> String {
+        chrono::DateTime::from_timestamp(self.timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| self.timestamp.to_string())
     }
-    Ok(())
 }
 
 #[tokio::main]
@@ -159,75 +162,126 @@ async fn main() -> Result<()> {
 
     if args.replay {
         // Replay mode: read from Redis and send to Telegram
-        replay_data(bot, chat_id, &mut con, &config.pairs).await?;
+        replay::replay_data(
+            bot,
+            chat_id,
+            con,
+            config.pairs.clone(),
+            args.replay_speed,
+            args.from,
+            args.until,
+        )
+        .await?;
         return Ok(());
     }
 
-    // Normal mode: monitor prices
-    let client = reqwest::Client::new();
+    // Normal mode: pull prices from whichever source is configured (live
+    // Kraken by default, or a constant fixed rate) and react to each update
+    // as it arrives instead of polling on a fixed interval. The command
+    // listener answers user queries concurrently over its own connection.
+    let use_fixed_rate = args.fixed_rate || config.price_source.as_deref() == Some("fixed_rate");
+    let mut source: Box<dyn PriceSource> = if use_fixed_rate {
+        Box::new(FixedRateSource::new(&config.pairs)?)
+    } else {
+        Box::new(KrakenSource::new(kraken::connect(config.pairs.clone()).await?))
+    };
+
+    let mut command_con = client.get_connection()?;
+    let _: () = redis::cmd("SELECT")
+        .arg(config.redis.database)
+        .query(&mut command_con)?;
+
+    tokio::select! {
+        result = monitor_prices(source.as_mut(), &bot, chat_id, &mut con, &config.pairs) => result,
+        _ = commands::run(bot.clone(), chat_id, command_con, config.pairs.clone()) => unreachable!("commands::run never returns"),
+    }
+}
 
+async fn monitor_prices(
+    source: &mut dyn PriceSource,
+    bot: &Bot,
+    chat_id: i64,
+    con: &mut redis::Connection,
+    pairs: &[PairConfig],
+) -> Result<()> {
     loop {
-        for pair_config in &config.pairs {
-            let url = format!(
-                "https://api.kraken.com/0/public/Ticker?pair={}",
-                pair_config.pair
-            );
-
-            match client.get(&url).send().await {
-                Ok(response) => {
-                    match response.json::<KrakenResponse>().await {
-                        Ok(kraken_data) => {
-                            if !kraken_data.error.is_empty() {
-                                eprintln!("Kraken API error: {:?}", kraken_data.error);
-                                continue;
-                            }
-
-                            // Extract price from the response
-                            let price = kraken_data.result
-                                .as_object()
-                                .and_then(|obj| obj.values().next())
-                                .and_then(|pair| pair.get("c"))
-                                .and_then(|c| c.get(0))
-                                .and_then(|p| p.as_str())
-                                .unwrap_or("N/A");
-
-                            let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
-
-                            // Store data in Redis
-                            let price_data = PriceData {
-                                price: price.to_string(),
-                                timestamp: timestamp.clone(),
-                            };
-                            
-                            let json = serde_json::to_string(&price_data)?;
-                            let _: () = con.set(&pair_config.pair, &json)?;
-                            
-                            // Also store in a time series (last 24 hours)
-                            let key = format!("history:{}", pair_config.pair);
-                            let _: () = con.lpush(&key, json.clone())?;
-                            let _: () = con.ltrim(&key, 0, 23)?; // Keep last 24 entries
-
-                            // Format message
-                            let message = format!(
-                                "🔔 Price Update\n\nPair: {}\nPrice: ${}\nTime: {}",
-                                pair_config.pair,
-                                price,
-                                timestamp
-                            );
-
-                            // Send to Telegram
-                            if let Err(e) = bot.send_message(ChatId(chat_id), message).await {
-                                eprintln!("Failed to send Telegram message: {}", e);
-                            }
-                        }
-                        Err(e) => eprintln!("Failed to parse Kraken response: {}", e),
-                    }
-                }
-                Err(e) => eprintln!("Failed to fetch from Kraken: {}", e),
+        for pair_config in pairs {
+            let price_data = source.latest_price(&pair_config.pair).await?;
+
+            // Streaming sources already pace themselves by blocking until a
+            // new update arrives; non-streaming ones (e.g. FixedRateSource) return
+            // instantly, so without this the loop would spin at 100% CPU
+            // and flood Redis/Telegram.
+            if !source.is_streaming() {
+                tokio::time::sleep(pair_config.interval_duration()).await;
+            }
+
+            let price: f64 = price_data.price.parse().unwrap_or(f64::NAN);
+
+            // Look up the last stored value before we overwrite it, so
+            // alert rules can compare against it.
+            let previous: Option<f64> = con
+                .get::<_, Option<String>>(&pair_config.pair)?
+                .and_then(|json| serde_json::from_str::<PriceData>(&json).ok())
+                .and_then(|data| data.price.parse().ok());
+
+            // Store data in Redis
+            let json = serde_json::to_string(&price_data)?;
+            let _: () = con.set(&pair_config.pair, &json)?;
+
+            // Also store in a sorted set keyed by epoch, so replay can pull
+            // an exact time range and retention can be enforced precisely.
+            let key = format!("history:{}", pair_config.pair);
+            let _: () = con.zadd(&key, json, price_data.timestamp)?;
+            trim_history(con, &key, pair_config)?;
+
+            let reason = alerts::evaluate(pair_config, previous, price);
+            if reason.is_none() && !pair_config.heartbeat {
+                continue;
+            }
+
+            let message = match &reason {
+                Some(reason) => format!(
+                    "🚨 Alert: {}\n\nPair: {}\nPrice: ${}\nTime: {}",
+                    reason,
+                    pair_config.pair,
+                    price_data.price,
+                    price_data.display_time()
+                ),
+                None => format!(
+                    "🔔 Price Update\n\nPair: {}\nPrice: ${}\nTime: {}",
+                    pair_config.pair,
+                    price_data.price,
+                    price_data.display_time()
+                ),
+            };
+
+            // Send to Telegram
+            if let Err(e) = bot.send_message(ChatId(chat_id), message).await {
+                eprintln!("Failed to send Telegram message: {}", e);
             }
         }
+    }
+}
+
+/// Enforces a pair's configured retention on its history sorted set: by age
+/// (`retain_seconds`), by count (`retain_count`), or both if both are set.
+/// If neither is configured, falls back to [`DEFAULT_RETAIN_SECONDS`] so
+/// retention still corresponds to an actual span of time regardless of how
+/// often this pair ticks.
+fn trim_history(con: &mut redis::Connection, key: &str, pair_config: &PairConfig) -> Result<()> {
+    let retain_seconds = pair_config
+        .retain_seconds
+        .or(pair_config.retain_count.is_none().then_some(DEFAULT_RETAIN_SECONDS));
+
+    if let Some(retain_seconds) = retain_seconds {
+        let cutoff = Utc::now().timestamp() - retain_seconds;
+        let _: () = con.zrembyscore(key, "-inf", cutoff)?;
+    }
 
-        // Wait for the specified interval
-        time::sleep(Duration::from_secs(3600)).await; // Default 1h interval
+    if let Some(retain_count) = pair_config.retain_count {
+        let _: () = con.zremrangebyrank(key, 0, -(retain_count + 1))?;
     }
+
+    Ok(())
 }
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::kraken;
+use crate::{PairConfig, PriceData};
+
+/// Something that can produce the latest price for a trading pair. The live
+/// Kraken client and the deterministic `FixedRateSource` fallback both implement
+/// this so the rest of the bot (and its tests) don't need to care which one
+/// is behind `&mut dyn PriceSource`.
+#[async_trait]
+pub(crate) trait PriceSource {
+    async fn latest_price(&mut self, pair: &str) -> Result<PriceData>;
+
+    /// Whether this source already paces itself (e.g. by blocking on a live
+    /// feed) between calls. Sources that return `false` have `latest_price`
+    /// return immediately, so the caller must pace its own polling loop
+    /// instead of spinning.
+    fn is_streaming(&self) -> bool {
+        true
+    }
+}
+
+/// Live prices streamed from Kraken over WebSocket. Each call to
+/// `latest_price` waits for the next update on that pair's own channel, so
+/// the same price is never reported twice and a quiet pair never blocks on
+/// another pair's traffic.
+pub(crate) struct KrakenSource {
+    updates: kraken::PriceUpdates,
+}
+
+impl KrakenSource {
+    pub(crate) fn new(updates: kraken::PriceUpdates) -> Self {
+        Self { updates }
+    }
+}
+
+#[async_trait]
+impl PriceSource for KrakenSource {
+    async fn latest_price(&mut self, pair: &str) -> Result<PriceData> {
+        let update = self.updates.next(pair).await?;
+        Ok(PriceData {
+            price: update.price,
+            timestamp: Utc::now().timestamp(),
+        })
+    }
+}
+
+/// A constant price per pair, configured in `config.yaml`. Used for
+/// deterministic tests and as a degraded mode when Kraken is unreachable.
+pub(crate) struct FixedRateSource {
+    prices: HashMap<String, String>,
+}
+
+impl FixedRateSource {
+    pub(crate) fn new(pairs: &[PairConfig]) -> Result<Self> {
+        let mut prices = HashMap::new();
+        for pair_config in pairs {
+            let price = pair_config
+                .fixed_price
+                .clone()
+                .with_context(|| format!("no fixed_price configured for pair {}", pair_config.pair))?;
+            prices.insert(pair_config.pair.clone(), price);
+        }
+        Ok(Self { prices })
+    }
+}
+
+#[async_trait]
+impl PriceSource for FixedRateSource {
+    async fn latest_price(&mut self, pair: &str) -> Result<PriceData> {
+        let price = self
+            .prices
+            .get(pair)
+            .with_context(|| format!("no fixed_price configured for pair {}", pair))?
+            .clone();
+
+        Ok(PriceData {
+            price,
+            timestamp: Utc::now().timestamp(),
+        })
+    }
+
+    fn is_streaming(&self) -> bool {
+        false
+    }
+}
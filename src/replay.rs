@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use redis::Commands;
+use teloxide::prelude::*;
+use teloxide::RequestError;
+use tokio::sync::mpsc;
+
+use crate::{PairConfig, PriceData};
+
+const CHUNK_SIZE: isize = 100; // Process 100 entries at a time
+const CHANNEL_CAPACITY: usize = 100;
+const BASE_DELAY: Duration = Duration::from_millis(100);
+
+struct ReplayEntry {
+    pair: String,
+    data: PriceData,
+}
+
+/// Resends stored prices from Redis to Telegram, oldest-window first,
+/// limited to timestamps within `[from, until]` when either bound is given.
+/// Reading and sending run as separate tasks joined by a bounded channel, so
+/// a slow Telegram sender applies backpressure to the reader instead of
+/// letting it race ahead and grow memory unbounded.
+///
+/// Deliberately takes a `redis::Connection` rather than `&mut dyn
+/// PriceSource`: replay re-sends history already recorded in Redis, it
+/// doesn't consume a live feed, so there's no price source to plug in here.
+pub(crate) async fn replay_data(
+    bot: Bot,
+    chat_id: i64,
+    mut redis_con: redis::Connection,
+    pairs: Vec<PairConfig>,
+    speed: f64,
+    from: Option<i64>,
+    until: Option<i64>,
+) -> Result<()> {
+    if !(speed > 0.0) {
+        anyhow::bail!("--replay-speed must be greater than 0, got {}", speed);
+    }
+
+    let (tx, mut rx) = mpsc::channel::<ReplayEntry>(CHANNEL_CAPACITY);
+
+    let min = from.map(|t| t.to_string()).unwrap_or_else(|| "-inf".to_string());
+    let max = until.map(|t| t.to_string()).unwrap_or_else(|| "+inf".to_string());
+
+    let reader = tokio::spawn(async move {
+        for pair_config in &pairs {
+            let key = format!("history:{}", pair_config.pair);
+            let mut offset: isize = 0;
+
+            loop {
+                // ZRANGEBYSCORE with LIMIT lets us page through an exact
+                // [from, until] range without pulling the whole set at once.
+                let chunk: Vec<String> =
+                    redis_con.zrangebyscore_limit(&key, min.as_str(), max.as_str(), offset, CHUNK_SIZE)?;
+                if chunk.is_empty() {
+                    break;
+                }
+
+                for json in &chunk {
+                    if let Ok(data) = serde_json::from_str::<PriceData>(json) {
+                        let entry = ReplayEntry {
+                            pair: pair_config.pair.clone(),
+                            data,
+                        };
+                        // A full channel blocks here, which is exactly the
+                        // backpressure we want: stop reading ahead until
+                        // the sender has caught up.
+                        if tx.send(entry).await.is_err() {
+                            return Ok::<(), anyhow::Error>(());
+                        }
+                    }
+                }
+
+                offset += chunk.len() as isize;
+            }
+        }
+        Ok(())
+    });
+
+    // --replay-speed compresses (>1.0) or expands (<1.0) the spacing
+    // between messages relative to the original 100ms pace. Validated above
+    // to be > 0; a speed so small that the delay would overflow `Duration`
+    // is rejected here instead of panicking.
+    let delay = Duration::try_from_secs_f64(BASE_DELAY.as_secs_f64() / speed)
+        .with_context(|| format!("--replay-speed {} is too small", speed))?;
+
+    while let Some(entry) = rx.recv().await {
+        let message = format!(
+            "🔄 Historical Price\n\nPair: {}\nPrice: ${}\nTime: {}",
+            entry.pair,
+            entry.data.price,
+            entry.data.display_time()
+        );
+
+        loop {
+            match bot.send_message(ChatId(chat_id), message.clone()).await {
+                Ok(_) => break,
+                Err(RequestError::RetryAfter(seconds)) => {
+                    // Telegram told us exactly how long to back off; honor
+                    // it instead of guessing a fixed delay.
+                    tokio::time::sleep(seconds.duration()).await;
+                }
+                Err(e) => {
+                    eprintln!("Failed to send Telegram message: {}", e);
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(delay).await;
+    }
+
+    reader
+        .await
+        .context("Replay reader task panicked")?
+        .context("Replay reader task failed")?;
+
+    Ok(())
+}